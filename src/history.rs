@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+/// Keystrokes committed within this many milliseconds of the previous
+/// commit are coalesced into it, so a burst of typing undoes as one step.
+const COALESCE_WINDOW_MS: f64 = 300.0;
+
+#[derive(Debug, Clone)]
+struct Revision {
+    code: String,
+    timestamp_ms: f64,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// A tree of code revisions backing undo/redo for [`CodeInput`](crate::pages::home).
+///
+/// Unlike the browser's built-in undo stack, revisions form a tree rather
+/// than a linear list: undoing and then typing something new branches off
+/// the current revision instead of discarding the redo history, and
+/// `current` always points at the active node. Keystrokes made less than
+/// `COALESCE_WINDOW_MS` apart are merged into the same revision so a burst
+/// of typing undoes as a unit.
+#[derive(Debug, Clone)]
+pub struct History {
+    revisions: Vec<Revision>,
+    current: usize,
+}
+
+impl History {
+    pub fn new(initial_code: impl Into<String>, now_ms: f64) -> Self {
+        Self {
+            revisions: vec![Revision {
+                code: initial_code.into(),
+                timestamp_ms: now_ms,
+                parent: None,
+                children: Vec::new(),
+            }],
+            current: 0,
+        }
+    }
+
+    pub fn current_code(&self) -> &str {
+        &self.revisions[self.current].code
+    }
+
+    /// Records `code` as a mutation at `now_ms`, coalescing into the current
+    /// revision if it falls within the idle-gap window of its timestamp. The
+    /// root revision (the original snapshot passed to `new`) is never
+    /// coalesced into, so it stays reachable via `undo`.
+    pub fn commit(&mut self, code: impl Into<String>, now_ms: f64) {
+        let code = code.into();
+        let cur = &mut self.revisions[self.current];
+        if cur.parent.is_some() && now_ms - cur.timestamp_ms < COALESCE_WINDOW_MS {
+            cur.code = code;
+            cur.timestamp_ms = now_ms;
+            return;
+        }
+
+        let parent = self.current;
+        let new_idx = self.revisions.len();
+        self.revisions.push(Revision {
+            code,
+            timestamp_ms: now_ms,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.revisions[parent].children.push(new_idx);
+        self.current = new_idx;
+    }
+
+    /// Moves to the parent revision, if any.
+    pub fn undo(&mut self) -> Option<&str> {
+        let parent = self.revisions[self.current].parent?;
+        self.current = parent;
+        Some(self.current_code())
+    }
+
+    /// Moves to the most recently created child revision, if any.
+    pub fn redo(&mut self) -> Option<&str> {
+        let child = *self.revisions[self.current].children.last()?;
+        self.current = child;
+        Some(self.current_code())
+    }
+
+    /// Replays up to `n` undos at once, stopping early at the root. Only
+    /// returns `None` (with no mutation) when already at the root.
+    pub fn earlier(&mut self, n: usize) -> Option<&str> {
+        self.revisions[self.current].parent?;
+        for _ in 0..n {
+            if self.undo().is_none() {
+                break;
+            }
+        }
+        Some(self.current_code())
+    }
+
+    /// Replays up to `n` redos at once, stopping early at a leaf. Only
+    /// returns `None` (with no mutation) when already at a leaf.
+    pub fn later(&mut self, n: usize) -> Option<&str> {
+        self.revisions[self.current].children.last()?;
+        for _ in 0..n {
+            if self.redo().is_none() {
+                break;
+            }
+        }
+        Some(self.current_code())
+    }
+
+    /// Travels back through a contiguous run of revisions, each committed
+    /// within `window` of its immediate predecessor (not of the revision the
+    /// jump started from — that would let timestamps drift past `window` one
+    /// small gap at a time). Always moves at least one revision if a parent
+    /// exists; only returns `None` (with no mutation) when already at the
+    /// root.
+    pub fn earlier_by(&mut self, window: Duration) -> Option<&str> {
+        let window_ms = window.as_secs_f64() * 1000.0;
+        let parent = self.revisions[self.current].parent?;
+        self.current = parent;
+        while let Some(parent) = self.revisions[self.current].parent {
+            let gap = self.revisions[self.current].timestamp_ms - self.revisions[parent].timestamp_ms;
+            if gap > window_ms {
+                break;
+            }
+            self.current = parent;
+        }
+        Some(self.current_code())
+    }
+
+    /// Mirror of `earlier_by`: travels forward through a contiguous run of
+    /// revisions each within `window` of its immediate predecessor. Always
+    /// moves at least one revision if a child exists; only returns `None`
+    /// (with no mutation) when already at a leaf.
+    pub fn later_by(&mut self, window: Duration) -> Option<&str> {
+        let window_ms = window.as_secs_f64() * 1000.0;
+        let child = *self.revisions[self.current].children.last()?;
+        self.current = child;
+        while let Some(&child) = self.revisions[self.current].children.last() {
+            let gap = self.revisions[child].timestamp_ms - self.revisions[self.current].timestamp_ms;
+            if gap > window_ms {
+                break;
+            }
+            self.current = child;
+        }
+        Some(self.current_code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_never_coalesces() {
+        let mut h = History::new("a", 0.0);
+        h.commit("ab", 100.0);
+        assert_eq!(h.current_code(), "ab");
+        assert_eq!(h.undo(), Some("a"));
+        assert_eq!(h.redo(), Some("ab"));
+    }
+
+    #[test]
+    fn rapid_commits_coalesce_but_a_slow_one_branches_off() {
+        let mut h = History::new("a", 0.0);
+        h.commit("ab", 1000.0);
+        h.commit("abc", 1100.0); // within COALESCE_WINDOW_MS of the last commit
+        assert_eq!(h.current_code(), "abc");
+        assert_eq!(h.undo(), Some("a"));
+        h.redo();
+
+        h.commit("abcd", 2000.0); // well outside the window -> new revision
+        assert_eq!(h.current_code(), "abcd");
+        assert_eq!(h.undo(), Some("abc"));
+    }
+
+    #[test]
+    fn undo_then_commit_branches_instead_of_discarding_redo() {
+        let mut h = History::new("a", 0.0);
+        h.commit("b", 1000.0);
+        h.commit("c", 2000.0);
+
+        assert_eq!(h.undo(), Some("b"));
+        h.commit("d", 3000.0);
+        assert_eq!(h.current_code(), "d");
+
+        assert_eq!(h.undo(), Some("b"));
+        // "d" is the most recently created child of "b", so redo prefers it
+        // over the now-orphaned "c" branch.
+        assert_eq!(h.redo(), Some("d"));
+    }
+
+    #[test]
+    fn earlier_by_returns_none_without_mutation_at_root() {
+        let mut h = History::new("a", 0.0);
+        assert_eq!(h.earlier_by(Duration::from_millis(300)), None);
+        assert_eq!(h.current_code(), "a");
+    }
+
+    #[test]
+    fn earlier_by_always_moves_at_least_one_step() {
+        let mut h = History::new("a", 0.0);
+        h.commit("b", 5000.0); // gap from root is far outside the window
+        assert_eq!(h.earlier_by(Duration::from_millis(300)), Some("a"));
+    }
+
+    // Gaps between commits below are kept >= COALESCE_WINDOW_MS so each one
+    // lands on its own revision instead of merging into the previous commit.
+
+    #[test]
+    fn earlier_by_stops_at_the_first_gap_exceeding_the_window() {
+        let mut h = History::new("a", 0.0);
+        h.commit("b", 1000.0);
+        h.commit("c", 1300.0); // 300ms after "b"
+        h.commit("d", 2000.0); // 700ms after "c"
+
+        // From "d", a 200ms window allows the mandatory first step (to "c")
+        // but the next gap (300ms, to "b") exceeds it, so the walk stops.
+        assert_eq!(h.earlier_by(Duration::from_millis(200)), Some("c"));
+    }
+
+    #[test]
+    fn earlier_by_keeps_going_while_each_adjacent_gap_fits_the_window() {
+        let mut h = History::new("a", 0.0);
+        h.commit("b", 1000.0);
+        h.commit("c", 1300.0); // 300ms after "b": within a 500ms window
+        h.commit("d", 2000.0); // 700ms after "c": outside a 500ms window
+
+        // From "d", the window comfortably covers the "c"->"b" gap (300ms)
+        // but not the "b"->root gap (1000ms), so the walk stops at "b"
+        // rather than after one step or all the way to the root.
+        assert_eq!(h.earlier_by(Duration::from_millis(500)), Some("b"));
+    }
+
+    #[test]
+    fn later_by_mirrors_earlier_by() {
+        let mut h = History::new("a", 0.0);
+        h.commit("b", 1000.0);
+        h.commit("c", 1300.0);
+        h.commit("d", 2000.0);
+        h.earlier(3);
+        assert_eq!(h.current_code(), "a");
+
+        assert_eq!(h.later_by(Duration::from_millis(500)), Some("c"));
+    }
+}
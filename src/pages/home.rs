@@ -1,22 +1,181 @@
+use crate::history::History;
 use leptos::prelude::window;
 use leptos::prelude::*;
 use leptos::wasm_bindgen::JsCast;
 use leptos::web_sys::{HtmlElement, HtmlSpanElement, HtmlTextAreaElement};
-use lsp_types::{Diagnostic, Position, Range};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, TextEdit};
 use shapels::analyze_source;
 use std::collections::HashSet;
 use std::rc::Rc;
+use std::time::Duration;
+
+/// Minimum length of the word under the cursor before completions are
+/// offered unprompted (a `.` always triggers them regardless of length).
+const MIN_COMPLETION_WORD_LEN: usize = 2;
+
+/// Number of revisions jumped by the Ctrl-Alt-ArrowLeft/Right history shortcuts.
+const MULTI_STEP_JUMP: usize = 5;
 
 #[derive(Debug, Clone)]
 struct LineRender {
     segments: Vec<RenderSegment>,
-    virtual_texts: Vec<String>,
+    /// Diagnostic messages for this line, each paired with the quick-fixes
+    /// available for it (empty if none apply).
+    virtual_texts: Vec<(String, Option<DiagnosticSeverity>, Vec<CodeAction>)>,
+    /// Persistent inlay hints for this line, as `(column, annotation)` pairs
+    /// where `column` is a char offset from the start of the line.
+    inlay_hints: Vec<(usize, String)>,
+}
+
+/// A suggested fix for a diagnostic: a title to show on the affordance and
+/// the edits to apply to `code` if the user accepts it.
+#[derive(Debug, Clone)]
+struct CodeAction {
+    title: String,
+    edits: Vec<TextEdit>,
+}
+
+/// Finds the byte offset right after the operand touching `range_end`, by
+/// walking left past any trailing whitespace/punctuation (e.g. a diagnostic
+/// range that overshoots into a trailing `)` or newline) until hitting an
+/// identifier character. This is what the `.T` quick-fix below anchors to,
+/// rather than trusting the diagnostic's raw end offset to land exactly on
+/// the operand.
+fn operand_insertion_offset(code: &str, range_end: usize) -> usize {
+    let mut end = range_end.min(code.len());
+    while end > 0 {
+        let ch = code[..end].chars().next_back().unwrap();
+        if ch.is_alphanumeric() || ch == '_' {
+            break;
+        }
+        end -= ch.len_utf8();
+    }
+    end
+}
+
+/// The diagnostic `code` shapels sets on a binary tensor op whose operands
+/// can't be contracted (e.g. `x @ y` with incompatible trailing dims).
+/// Matching on this instead of the message text keeps quick-fixes from
+/// breaking if the diagnostic's wording ever changes.
+const SHAPE_MISMATCH_DIAGNOSTIC_CODE: &str = "shape-mismatch";
+
+/// The axis order that swaps an operand's last two dimensions, e.g. `[1, 0]`
+/// for a 2D tensor or `[0, 2, 1]` for a batched one — the fix that makes a
+/// matmul's trailing dims line up without touching the leading (batch) axes.
+fn swap_last_two_axes(rank: usize) -> Vec<usize> {
+    let mut axes: Vec<usize> = (0..rank).collect();
+    if rank >= 2 {
+        axes.swap(rank - 1, rank - 2);
+    }
+    axes
+}
+
+/// Quick-fixes for a shape-mismatch diagnostic, anchored right after the
+/// operand at `operand_end`. `operand_rank` is the right-hand operand's
+/// number of dimensions, if known, and enables the `.permute(...)` fix
+/// (there's no single correct `.reshape(...)` target for a shape mismatch,
+/// so that suggestion isn't offered here).
+fn code_actions_for_diagnostic(
+    diagnostic_code: Option<&str>,
+    operand_end: Position,
+    operand_rank: Option<usize>,
+) -> Vec<CodeAction> {
+    if diagnostic_code != Some(SHAPE_MISMATCH_DIAGNOSTIC_CODE) {
+        return Vec::new();
+    }
+
+    let edit_at = |new_text: &str| TextEdit {
+        range: Range {
+            start: operand_end,
+            end: operand_end,
+        },
+        new_text: new_text.to_string(),
+    };
+
+    let mut actions = vec![CodeAction {
+        title: "Insert .T to transpose the right-hand operand".to_string(),
+        edits: vec![edit_at(".T")],
+    }];
+
+    if let Some(rank) = operand_rank {
+        if rank >= 2 {
+            let axes = swap_last_two_axes(rank);
+            let axes_str = axes
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            actions.push(CodeAction {
+                title: format!(".permute({axes_str}) to swap the receiver's last two axes"),
+                edits: vec![edit_at(&format!(".permute({axes_str})"))],
+            });
+        }
+    }
+
+    actions
+}
+
+/// Applies `edits` to `code` in place, from last to first so earlier offsets
+/// stay valid as later ones are applied.
+fn apply_text_edits(code: &mut String, edits: &[TextEdit]) {
+    let mut offset_edits: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .filter_map(|edit| {
+            range_to_offsets(code, &edit.range).map(|(s, e)| (s, e, edit.new_text.as_str()))
+        })
+        .collect();
+    offset_edits.sort_by_key(|(s, _, _)| std::cmp::Reverse(*s));
+    for (s, e, new_text) in offset_edits {
+        code.replace_range(s..e, new_text);
+    }
 }
 
 #[derive(Debug, Clone)]
 struct RenderSegment {
     text: String,
-    has_diag: bool,
+    severity: Option<DiagnosticSeverity>,
+}
+
+/// Lower is more severe; used to pick the worst severity among diagnostics
+/// overlapping the same segment.
+fn severity_rank(severity: DiagnosticSeverity) -> u8 {
+    match severity {
+        DiagnosticSeverity::ERROR => 0,
+        DiagnosticSeverity::WARNING => 1,
+        DiagnosticSeverity::INFORMATION => 2,
+        DiagnosticSeverity::HINT => 3,
+        _ => 4,
+    }
+}
+
+fn severity_class(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "diag-range diag-error",
+        Some(DiagnosticSeverity::WARNING) => "diag-range diag-warning",
+        Some(DiagnosticSeverity::INFORMATION) => "diag-range diag-information",
+        Some(DiagnosticSeverity::HINT) => "diag-range diag-hint",
+        _ => "diag-range diag-none",
+    }
+}
+
+fn severity_virtual_class(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "diag-virtual diag-virtual-error",
+        Some(DiagnosticSeverity::WARNING) => "diag-virtual diag-virtual-warning",
+        Some(DiagnosticSeverity::INFORMATION) => "diag-virtual diag-virtual-information",
+        Some(DiagnosticSeverity::HINT) => "diag-virtual diag-virtual-hint",
+        _ => "diag-virtual",
+    }
+}
+
+fn severity_icon(severity: DiagnosticSeverity) -> &'static str {
+    match severity {
+        DiagnosticSeverity::ERROR => "✖",
+        DiagnosticSeverity::WARNING => "▲",
+        DiagnosticSeverity::INFORMATION => "ℹ",
+        DiagnosticSeverity::HINT => "💡",
+        _ => "•",
+    }
 }
 
 fn highlight_tokens(text: &str) -> Vec<(String, Option<&'static str>)> {
@@ -109,6 +268,77 @@ fn range_to_offsets(src: &str, range: &Range) -> Option<(usize, usize)> {
     Some((start.min(end), end.max(start)))
 }
 
+/// Inverse of `position_to_offset`. An offset that falls exactly on a line
+/// boundary (right after a `\n`) belongs to the *next* line at character 0,
+/// not the previous line's trailing position; only the last, newline-less
+/// line (or exact end-of-file, handled like `position_to_offset`'s own
+/// end-of-file case) keeps an offset at its own `line_end`.
+fn offset_to_position(src: &str, offset: usize) -> Option<Position> {
+    if offset > src.len() {
+        return None;
+    }
+
+    let mut line_start = 0usize;
+    for (line_idx, line) in src.split_inclusive('\n').enumerate() {
+        let line_end = line_start + line.len();
+        if offset < line_end || (offset == line_end && !line.ends_with('\n')) {
+            let character = src[line_start..offset].chars().count();
+            return Some(Position {
+                line: line_idx as u32,
+                character: character as u32,
+            });
+        }
+        line_start = line_end;
+    }
+
+    if offset == src.len() {
+        return Some(Position {
+            line: src.lines().count() as u32,
+            character: 0,
+        });
+    }
+
+    None
+}
+
+/// The browser reports the cursor position as a UTF-16 code unit index
+/// (`HTMLTextAreaElement.selectionStart`); convert it to the byte offset
+/// `position_to_offset`/`offset_to_position` work with. Walking `char_indices`
+/// and counting each char's UTF-16 width (rather than just its index) keeps
+/// this correct for astral-plane characters, which are one `char` but two
+/// UTF-16 code units.
+fn char_index_to_byte_offset(src: &str, utf16_idx: usize) -> usize {
+    let mut utf16_count = 0usize;
+    for (byte_idx, ch) in src.char_indices() {
+        if utf16_count >= utf16_idx {
+            return byte_idx;
+        }
+        utf16_count += ch.len_utf16();
+    }
+    src.len()
+}
+
+/// The `[start, end)` byte range of the identifier-like word touching
+/// `offset`.
+fn word_bounds_at(code: &str, offset: usize) -> (usize, usize) {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut start = offset;
+    for (idx, ch) in code[..offset].char_indices().rev() {
+        if !is_word(ch) {
+            break;
+        }
+        start = idx;
+    }
+    let mut end = offset;
+    for (idx, ch) in code[offset..].char_indices() {
+        if !is_word(ch) {
+            break;
+        }
+        end = offset + idx + ch.len_utf8();
+    }
+    (start, end)
+}
+
 fn render_hover_text(info: &shapels::HoverInfo) -> String {
     if let Some(shape) = &info.shape {
         format!(
@@ -121,16 +351,39 @@ fn render_hover_text(info: &shapels::HoverInfo) -> String {
     }
 }
 
+/// Short form of a hover's shape, suitable for an inline inlay hint rather
+/// than the fuller `render_hover_text` used by the hover popup.
+fn render_inlay_annotation(info: &shapels::HoverInfo) -> Option<String> {
+    info.shape.as_ref().map(|shape| shape.render())
+}
+
+fn byte_to_char_column(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset.min(line.len())].chars().count()
+}
+
+fn diagnostic_code_string(code: &Option<NumberOrString>) -> Option<String> {
+    match code {
+        Some(NumberOrString::String(s)) => Some(s.clone()),
+        Some(NumberOrString::Number(n)) => Some(n.to_string()),
+        None => None,
+    }
+}
+
 fn split_lines_with_metadata(
     code: &str,
     diagnostics: &[Diagnostic],
     hover_entries: &[(Range, shapels::HoverInfo)],
 ) -> Vec<LineRender> {
-    let mut diag_ranges: Vec<(usize, usize, String)> = diagnostics
-        .iter()
-        .filter_map(|d| range_to_offsets(code, &d.range).map(|(s, e)| (s, e, d.message.clone())))
-        .collect();
-    diag_ranges.sort_by_key(|(s, _, _)| *s);
+    let mut diag_ranges: Vec<(usize, usize, String, Option<DiagnosticSeverity>, Option<String>)> =
+        diagnostics
+            .iter()
+            .filter_map(|d| {
+                range_to_offsets(code, &d.range).map(|(s, e)| {
+                    (s, e, d.message.clone(), d.severity, diagnostic_code_string(&d.code))
+                })
+            })
+            .collect();
+    diag_ranges.sort_by_key(|(s, _, _, _, _)| *s);
 
     let mut hover_ranges: Vec<(usize, usize, String)> = hover_entries
         .iter()
@@ -140,6 +393,18 @@ fn split_lines_with_metadata(
         .collect();
     hover_ranges.sort_by_key(|(s, _, _)| *s);
 
+    // Inlay hints are anchored at the end of their token, so only the end
+    // offset of each hover entry is kept.
+    let mut inlay_entries: Vec<(usize, String)> = hover_entries
+        .iter()
+        .filter_map(|(range, info)| {
+            let (_, end) = range_to_offsets(code, range)?;
+            let annotation = render_inlay_annotation(info)?;
+            Some((end, annotation))
+        })
+        .collect();
+    inlay_entries.sort_by_key(|(offset, _)| *offset);
+
     let mut lines = Vec::new();
     let mut line_start = 0usize;
 
@@ -148,9 +413,13 @@ fn split_lines_with_metadata(
         let line_end = line_start + line_len;
 
         let mut boundaries: Vec<usize> = vec![line_start, line_end];
-        for (s, e, _) in diag_ranges.iter().chain(hover_ranges.iter()) {
-            let start = (*s).max(line_start).min(line_end);
-            let end = (*e).max(line_start).min(line_end);
+        let range_bounds = diag_ranges
+            .iter()
+            .map(|(s, e, _, _, _)| (*s, *e))
+            .chain(hover_ranges.iter().map(|(s, e, _)| (*s, *e)));
+        for (s, e) in range_bounds {
+            let start = s.max(line_start).min(line_end);
+            let end = e.max(line_start).min(line_end);
             if start < end {
                 boundaries.push(start);
                 boundaries.push(end);
@@ -169,30 +438,53 @@ fn split_lines_with_metadata(
             }
             let text = line[(seg_start - line_start)..(seg_end - line_start)].to_string();
 
-            let has_diag = diag_ranges
+            let severity = diag_ranges
                 .iter()
-                .any(|(s, e, _)| seg_start < *e && seg_end > *s);
+                .filter(|(s, e, _, _, _)| seg_start < *e && seg_end > *s)
+                .filter_map(|(_, _, _, severity, _)| *severity)
+                .min_by_key(|severity| severity_rank(*severity));
 
-            segments.push(RenderSegment { text, has_diag });
+            segments.push(RenderSegment { text, severity });
         }
 
         if segments.is_empty() {
             segments.push(RenderSegment {
                 text: line.to_string(),
-                has_diag: false,
+                severity: None,
             });
         }
 
-        let mut virtual_texts = HashSet::new();
-        for (s, e, msg) in diag_ranges.iter() {
-            if *s < line_end && *e > line_start {
-                virtual_texts.insert(msg.clone());
+        let mut virtual_texts: Vec<(String, Option<DiagnosticSeverity>, Vec<CodeAction>)> =
+            Vec::new();
+        let mut seen_messages = HashSet::new();
+        for (s, e, msg, severity, diag_code) in diag_ranges.iter() {
+            if *s < line_end && *e > line_start && seen_messages.insert(msg.clone()) {
+                let anchor = operand_insertion_offset(code, *e);
+                let operand_rank = inlay_entries
+                    .iter()
+                    .find(|(offset, _)| *offset == anchor)
+                    .map(|(_, annotation)| annotation.split_whitespace().count());
+                let actions = offset_to_position(code, anchor)
+                    .map(|end| {
+                        code_actions_for_diagnostic(diag_code.as_deref(), end, operand_rank)
+                    })
+                    .unwrap_or_default();
+                virtual_texts.push((msg.clone(), *severity, actions));
             }
         }
 
+        let inlay_hints: Vec<(usize, String)> = inlay_entries
+            .iter()
+            .filter(|(offset, _)| *offset >= line_start && *offset <= line_end)
+            .map(|(offset, annotation)| {
+                (byte_to_char_column(line, offset - line_start), annotation.clone())
+            })
+            .collect();
+
         lines.push(LineRender {
             segments,
-            virtual_texts: virtual_texts.into_iter().collect(),
+            virtual_texts,
+            inlay_hints,
         });
 
         // account for the stripped '\n'
@@ -204,9 +496,10 @@ fn split_lines_with_metadata(
         lines.push(LineRender {
             segments: vec![RenderSegment {
                 text: String::new(),
-                has_diag: false,
+                severity: None,
             }],
             virtual_texts: Vec::new(),
+            inlay_hints: Vec::new(),
         });
     }
 
@@ -224,10 +517,66 @@ fn CodeInput<'a>(initial_code: &'a str) -> impl IntoView {
     let overlay_ref = NodeRef::<leptos::html::Pre>::new();
     let measure_ref = NodeRef::<leptos::html::Span>::new();
     let (hover_popup, set_hover_popup) = signal(None::<(usize, f64, String)>);
+    let (show_inlay_hints, set_show_inlay_hints) = signal(false);
+    let (completion_items, set_completion_items) = signal(Vec::<lsp_types::CompletionItem>::new());
+    let (completion_popup, set_completion_popup) = signal(None::<(f64, f64)>);
+    let (completion_selected, set_completion_selected) = signal(0usize);
+    let (completion_replace_whole_word, set_completion_replace_whole_word) = signal(false);
+    let (signature_help_popup, set_signature_help_popup) =
+        signal(None::<(f64, f64, shapels::SignatureHelp)>);
     let analysis_store = StoredValue::new_local(Rc::new(analyze_source(initial_code)));
+    let history = StoredValue::new_local(History::new(initial_code.to_string(), now_ms()));
+
+    // Replaces the word under the cursor with `label`; whether that means
+    // just the partial word typed so far or the whole word is governed by
+    // `completion_replace_whole_word`.
+    let apply_completion = move |label: String| {
+        let Some(textarea) = text_ref.get() else { return };
+        let current = code.get_untracked();
+        let cursor_chars = textarea.selection_start().ok().flatten().unwrap_or(0) as usize;
+        let cursor = char_index_to_byte_offset(&current, cursor_chars);
+        let (word_start, word_end) = word_bounds_at(&current, cursor);
+        let replace_end = if completion_replace_whole_word.get_untracked() {
+            word_end
+        } else {
+            cursor
+        };
+
+        let mut updated = current.clone();
+        updated.replace_range(word_start..replace_end, &label);
+        set_code.set(updated.clone());
+        history.update_value(|h| h.commit(updated, now_ms()));
+    };
+
+    // Applies the code a history jump (undo/redo/earlier/later/...) landed
+    // on, and re-runs analysis against it since a jump doesn't go through the
+    // input handler, leaving only the overlay's lazy render closure to
+    // refresh analysis_store otherwise.
+    let apply_history_jump = move |next: Option<String>| {
+        if let Some(code_str) = next {
+            set_code.set(code_str);
+            analysis_store.set_value(Rc::new(analyze_source(&code.get_untracked())));
+        }
+    };
 
     view! {
         <div class="code-wrapper">
+            <label class="inlay-hint-toggle">
+                <input
+                    type="checkbox"
+                    on:change=move |ev| set_show_inlay_hints.set(event_target_checked(&ev))
+                />
+                " Show inferred shapes"
+            </label>
+            <label class="completion-replace-toggle">
+                <input
+                    type="checkbox"
+                    on:change=move |ev| {
+                        set_completion_replace_whole_word.set(event_target_checked(&ev))
+                    }
+                />
+                " Completions replace whole word"
+            </label>
             <pre class="code-overlay" aria-hidden="true" node_ref=overlay_ref>
                 {move || {
                     // refresh analysis once per render
@@ -242,11 +591,7 @@ fn CodeInput<'a>(initial_code: &'a str) -> impl IntoView {
                     .enumerate()
                     .map(|(_line_idx, line)| {
                             let segments = line.segments.into_iter().map(|segment| {
-                                let range_class = if segment.has_diag {
-                                    "diag-range"
-                                } else {
-                                    "diag-range diag-none"
-                                };
+                                let range_class = severity_class(segment.severity);
                                 let tokens = highlight_tokens(&segment.text);
                                 view! {
                                     <span class="code-span">
@@ -264,15 +609,65 @@ fn CodeInput<'a>(initial_code: &'a str) -> impl IntoView {
                             let virtuals: Vec<_> = line
                                 .virtual_texts
                                 .into_iter()
-                                .map(|msg| {
-                                    view! { <span class="diag-virtual">{" ⟫ "}{msg}</span> }
-                                        .into_view()
+                                .map(|(msg, severity, actions)| {
+                                    let cls = severity_virtual_class(severity);
+                                    let icon = severity.map(severity_icon).unwrap_or("•");
+                                    let quick_fix = (!actions.is_empty()).then(|| {
+                                        view! {
+                                            <button
+                                                class="diag-quick-fix"
+                                                title=actions[0].title.clone()
+                                                on:click=move |_| {
+                                                    let mut updated = code.get_untracked();
+                                                    apply_text_edits(&mut updated, &actions[0].edits);
+                                                    set_code.set(updated.clone());
+                                                    history.update_value(|h| h.commit(updated, now_ms()));
+                                                }
+                                            >
+                                                "💡"
+                                            </button>
+                                        }
+                                    });
+                                    view! {
+                                        <span class=cls>
+                                            {" ⟫ "}
+                                            {icon}
+                                            {" "}
+                                            {msg}
+                                            {quick_fix}
+                                        </span>
+                                    }
+                                    .into_view()
                                 })
                                 .collect();
 
+                            let inlay_spans: Vec<_> = if show_inlay_hints.get() {
+                                let char_w = text_ref
+                                    .get()
+                                    .zip(measure_ref.get())
+                                    .and_then(|(textarea, measure)| measure_metrics(&textarea, &measure))
+                                    .map(|(char_w, _, _, _)| char_w)
+                                    .unwrap_or(0.0);
+                                line.inlay_hints
+                                    .into_iter()
+                                    .map(|(column, annotation)| {
+                                        let left = column as f64 * char_w;
+                                        view! {
+                                            <span class="inlay-hint" style=format!("left: {left}px;")>
+                                                {format!(" : {annotation}")}
+                                            </span>
+                                        }
+                                        .into_view()
+                                    })
+                                    .collect()
+                            } else {
+                                Vec::new()
+                            };
+
                         view! {
                             <div class="code-line">
                                 <span class="code-line-text">{segments.collect_view()}</span>
+                                <span class="inlay-hints">{inlay_spans.into_iter().collect_view()}</span>
                                 <span class="diag-line-messages">{virtuals.into_iter().collect_view()}</span>
                             </div>
                         }
@@ -283,11 +678,172 @@ fn CodeInput<'a>(initial_code: &'a str) -> impl IntoView {
             </pre>
             <textarea
                 class="code-input"
-                // update the signal on each keystroke
-                bind:value=(code, set_code)
+                prop:value=move || code.get()
                 spellcheck=false
                 wrap="off"
                 node_ref=text_ref
+                // update the signal on each keystroke and commit it into history
+                on:input=move |ev| {
+                    let value = event_target_value(&ev);
+                    set_code.set(value.clone());
+                    let now = now_ms();
+                    history.update_value(|h| h.commit(value.clone(), now));
+
+                    let Some(textarea) = text_ref.get() else { return };
+                    let cursor_chars = textarea.selection_start().ok().flatten().unwrap_or(0) as usize;
+                    let cursor = char_index_to_byte_offset(&value, cursor_chars);
+                    let Some(pos) = offset_to_position(&value, cursor) else { return };
+
+                    let triggered_by_dot = value[..cursor].ends_with('.');
+                    let (word_start, _) = word_bounds_at(&value, cursor);
+                    let word_len = value[word_start..cursor].chars().count();
+
+                    if !triggered_by_dot && word_len < MIN_COMPLETION_WORD_LEN {
+                        set_completion_items.set(Vec::new());
+                        set_completion_popup.set(None);
+                    } else {
+                        // Refresh analysis against `value` first: the overlay's
+                        // render closure won't re-run analyze_source until
+                        // after this handler returns, so without this the
+                        // completions below would be computed against the
+                        // previous keystroke's analysis.
+                        analysis_store.set_value(Rc::new(analyze_source(&value)));
+                        analysis_store.with_value(|analysis| {
+                            let items = analysis.completions(pos);
+                            set_completion_selected.set(0);
+                            if items.is_empty() {
+                                set_completion_items.set(Vec::new());
+                                set_completion_popup.set(None);
+                                return;
+                            }
+                            set_completion_items.set(items);
+                            if let Some(measure) = measure_ref.get() {
+                                if let Some((char_w, line_h, pad_left, pad_top)) =
+                                    measure_metrics(&textarea, &measure)
+                                {
+                                    let top = (pos.line as f64) * line_h - textarea.scroll_top() as f64
+                                        + pad_top;
+                                    let left = (pos.character as f64) * char_w
+                                        - textarea.scroll_left() as f64
+                                        + pad_left;
+                                    set_completion_popup.set(Some((top + line_h, left)));
+                                }
+                            }
+                        });
+                    }
+
+                    // Same staleness concern as the completions query above:
+                    // make sure this runs against `value`'s analysis, not the
+                    // previous keystroke's.
+                    analysis_store.set_value(Rc::new(analyze_source(&value)));
+                    analysis_store.with_value(|analysis| {
+                        let Some(help) = analysis.signature_help(pos) else {
+                            set_signature_help_popup.set(None);
+                            return;
+                        };
+                        if let Some(measure) = measure_ref.get() {
+                            if let Some((char_w, line_h, pad_left, pad_top)) =
+                                measure_metrics(&textarea, &measure)
+                            {
+                                let top = (pos.line as f64) * line_h - textarea.scroll_top() as f64
+                                    + pad_top;
+                                let left = (pos.character as f64) * char_w
+                                    - textarea.scroll_left() as f64
+                                    + pad_left;
+                                set_signature_help_popup.set(Some((top, left, help)));
+                            }
+                        }
+                    });
+                }
+                on:keydown=move |ev| {
+                    if !completion_items.get_untracked().is_empty() {
+                        match ev.key().as_str() {
+                            "ArrowDown" => {
+                                ev.prevent_default();
+                                let len = completion_items.get_untracked().len();
+                                set_completion_selected.update(|i| *i = (*i + 1) % len);
+                                return;
+                            }
+                            "ArrowUp" => {
+                                ev.prevent_default();
+                                let len = completion_items.get_untracked().len();
+                                set_completion_selected.update(|i| *i = (*i + len - 1) % len);
+                                return;
+                            }
+                            "Enter" | "Tab" => {
+                                ev.prevent_default();
+                                let idx = completion_selected.get_untracked();
+                                if let Some(item) = completion_items.get_untracked().get(idx) {
+                                    apply_completion(item.label.clone());
+                                }
+                                set_completion_items.set(Vec::new());
+                                set_completion_popup.set(None);
+                                return;
+                            }
+                            "Escape" => {
+                                set_completion_items.set(Vec::new());
+                                set_completion_popup.set(None);
+                                return;
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    if !ev.ctrl_key() {
+                        return;
+                    }
+
+                    // Ctrl-Alt-ArrowLeft/Right jump several revisions at once;
+                    // Ctrl-Alt-Z/Ctrl-Alt-Shift-Z jump by a coalesced time window.
+                    if ev.alt_key() {
+                        let shift = ev.shift_key();
+                        match ev.key().as_str() {
+                            "ArrowLeft" => {
+                                ev.prevent_default();
+                                let mut next = None;
+                                history.update_value(|h| {
+                                    next = h.earlier(MULTI_STEP_JUMP).map(String::from);
+                                });
+                                apply_history_jump(next);
+                            }
+                            "ArrowRight" => {
+                                ev.prevent_default();
+                                let mut next = None;
+                                history.update_value(|h| {
+                                    next = h.later(MULTI_STEP_JUMP).map(String::from);
+                                });
+                                apply_history_jump(next);
+                            }
+                            key if key.eq_ignore_ascii_case("z") => {
+                                ev.prevent_default();
+                                let mut next = None;
+                                history.update_value(|h| {
+                                    let window = Duration::from_millis(300);
+                                    next = if shift {
+                                        h.later_by(window)
+                                    } else {
+                                        h.earlier_by(window)
+                                    }
+                                    .map(String::from);
+                                });
+                                apply_history_jump(next);
+                            }
+                            _ => {}
+                        }
+                        return;
+                    }
+
+                    if !ev.key().eq_ignore_ascii_case("z") {
+                        return;
+                    }
+                    ev.prevent_default();
+                    let shift = ev.shift_key();
+                    let mut next = None;
+                    history.update_value(|h| {
+                        next = if shift { h.redo() } else { h.undo() }.map(String::from);
+                    });
+                    apply_history_jump(next);
+                }
                 on:scroll=move |_| {
                     if let (Some(textarea), Some(overlay)) = (text_ref.get(), overlay_ref.get()) {
                         overlay.set_scroll_left(textarea.scroll_left());
@@ -351,6 +907,75 @@ fn CodeInput<'a>(initial_code: &'a str) -> impl IntoView {
                         }
                     })
             }}
+            {move || {
+                completion_popup
+                    .get()
+                    .map(|(top, left)| {
+                        let selected = completion_selected.get();
+                        view! {
+                            <ul
+                                class="completion-popup"
+                                style=format!("top: {top}px; left: {left}px;")
+                            >
+                                {completion_items
+                                    .get()
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(idx, item)| {
+                                        let cls = if idx == selected {
+                                            "completion-item completion-item-selected"
+                                        } else {
+                                            "completion-item"
+                                        };
+                                        let label = item.label.clone();
+                                        view! {
+                                            <li
+                                                class=cls
+                                                on:mousedown=move |ev| {
+                                                    ev.prevent_default();
+                                                    apply_completion(label.clone());
+                                                    set_completion_items.set(Vec::new());
+                                                    set_completion_popup.set(None);
+                                                }
+                                            >
+                                                {item.label.clone()}
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ul>
+                        }
+                    })
+            }}
+            {move || {
+                signature_help_popup
+                    .get()
+                    .map(|(top, left, help)| {
+                        view! {
+                            <div
+                                class="signature-help-popup"
+                                style=format!("top: {top}px; left: {left}px;")
+                            >
+                                <span class="signature-help-label">{help.label.clone()}</span>
+                                <span class="signature-help-params">
+                                    {help
+                                        .params
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(idx, param)| {
+                                            let cls = if Some(idx) == help.active_param {
+                                                "signature-help-param signature-help-param-active"
+                                            } else {
+                                                "signature-help-param"
+                                            };
+                                            view! { <span class=cls>{param.clone()}</span> }
+                                        })
+                                        .collect_view()}
+                                </span>
+                            </div>
+                        }
+                    })
+            }}
         </div>
     }
 }
@@ -377,6 +1002,11 @@ fn parse_px(value: Option<String>) -> f64 {
         .and_then(|s| s.trim_end_matches("px").parse::<f64>().ok())
         .unwrap_or(0.0)
 }
+
+/// Current time in milliseconds, used to drive [`History`] coalescing.
+fn now_ms() -> f64 {
+    window().performance().map(|p| p.now()).unwrap_or(0.0)
+}
 /// Default Home Page
 #[component]
 pub fn Home() -> impl IntoView {